@@ -1,5 +1,6 @@
 use crate::Error;
 
+use futures::stream::{self, Stream};
 use hyper::client::HttpConnector;
 use hyper_proxy::{Intercept, Proxy, ProxyConnector};
 use rusoto_core::credential::ChainProvider;
@@ -9,20 +10,29 @@ use rusoto_athena::{
     Athena as AthenaTrait,
     AthenaClient,
 
+    EncryptionConfiguration,
     GetQueryExecutionInput,
     GetQueryExecutionOutput,
     GetQueryResultsInput,
+    GetQueryResultsOutput,
     ResultConfiguration,
     StartQueryExecutionInput,
+    StopQueryExecutionInput,
     QueryExecution,
     QueryExecutionContext,
-    QueryExecutionStatistics,
     QueryExecutionStatus,
 };
 
+use std::collections::VecDeque;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 
+mod params;
+pub use self::params::*;
+
+mod retry;
+pub use self::retry::*;
+
 mod types;
 pub use self::types::*;
 
@@ -30,11 +40,60 @@ pub struct Athena {
     client: AthenaClient,
     database: String,
     result_bucket: String,
-    workgroup: Option<String>
+    workgroup: Option<String>,
+    retry_policy: Box<dyn RetryPolicy>,
+    encryption: Option<EncryptionConfiguration>,
+}
+
+/// Extract column names and data rows (the header row is skipped) from a `get_query_results`
+/// page.
+fn parse_page(res: GetQueryResultsOutput) -> (Vec<String>, Vec<Vec<String>>) {
+    let resultset = res.result_set.expect("result_set was none");
+
+    let columns = resultset.result_set_metadata.expect("missing result_set_metadata")
+        .column_info.expect("missing column_info")
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+
+    let rows = resultset.rows.expect("missing result_set.rows")
+        .into_iter()
+        .skip(1) // headers..
+        .map(|row| {
+            row.data.expect("missing row.data")
+                .into_iter()
+                .map(|field| field.var_char_value.unwrap_or_else(String::new))
+                .collect()
+        })
+        .collect();
+
+    (columns, rows)
+}
+
+/// Drives the `stream::unfold` behind [`Athena::query_stream`], holding the already-fetched but
+/// not-yet-yielded rows for the current page plus whatever is needed to fetch the next one.
+struct PageStream {
+    client: AthenaClient,
+    query_execution_id: String,
+    next_token: Option<String>,
+    buffer: VecDeque<Vec<String>>,
+    done: bool,
 }
 
 impl Athena {
-    pub fn new(region: &str, database: &str, result_bucket: &str, workgroup: Option<String>) -> crate::Result<Self> {
+    pub fn new(
+        region: &str,
+        database: &str,
+        result_bucket: &str,
+        workgroup: Option<String>,
+        retry_policy: Box<dyn RetryPolicy>,
+        encryption_mode: Option<EncryptionMode>,
+        kms_key: Option<String>,
+    ) -> crate::Result<Self> {
+        if encryption_mode.map_or(false, |mode| mode.requires_kms_key()) && kms_key.is_none() {
+            return Err(Error::MissingKmsKey);
+        }
+
         // Create a new AthenaClient, using a HTTPS_PROXY if configured in the environment
         let client = match std::env::var("HTTPS_PROXY") {
             Ok(proxy_uri) => {
@@ -51,18 +110,117 @@ impl Athena {
             client,
             database: database.into(),
             result_bucket: result_bucket.into(),
-            workgroup
+            workgroup,
+            retry_policy,
+            encryption: encryption_mode.map(|mode| mode.to_encryption_configuration(kms_key)),
         })
     }
 
     pub async fn query(&self, query: &str) -> crate::Result<QueryResult> {
+        let query_execution_id = self.start_query(query).await?;
+        let mut result = self.wait_for_completion(&query_execution_id).await?;
+
+        // Fetch results in a loop and append
+        let mut result_req = GetQueryResultsInput {
+            next_token: None,
+            query_execution_id: result.query_execution_id.clone(),
+            ..Default::default()
+        };
+
+        loop {
+            let res = self.client.get_query_results(result_req.clone()).await?;
+            let next_token = res.next_token.clone();
+
+            let (columns, rows) = parse_page(res);
+            result.columns = columns;
+            rows.into_iter().for_each(|row| result.append_row(row));
+
+            tracing::trace!(
+                state = %"SUCCEEDED",
+                rows_read = %result.rows
+            );
+
+            if next_token.is_some() {
+                result_req.next_token = next_token;
+                continue;
+            }
+
+            break;
+        }
+
+        Ok(result)
+    }
+
+    /// Like [`Athena::query`], but yields rows page-by-page as they arrive instead of buffering
+    /// the whole resultset first.
+    pub async fn query_stream(&self, query: &str) -> crate::Result<(QueryResult, impl Stream<Item = crate::Result<Vec<String>>>)> {
+        let query_execution_id = self.start_query(query).await?;
+        let mut result = self.wait_for_completion(&query_execution_id).await?;
+
+        // Fetch the first page up front so the caller has `columns` available before the first
+        // row is yielded from the stream.
+        let first_page = self.client.get_query_results(GetQueryResultsInput {
+            next_token: None,
+            query_execution_id: result.query_execution_id.clone(),
+            ..Default::default()
+        }).await?;
+        let next_token = first_page.next_token.clone();
+
+        let (columns, rows) = parse_page(first_page);
+        result.columns = columns;
+        let buffer: VecDeque<Vec<String>> = rows.into_iter().collect();
+
+        let state = PageStream {
+            client: self.client.clone(),
+            query_execution_id: result.query_execution_id.clone(),
+            next_token,
+            buffer,
+            done: false,
+        };
+
+        let stream = stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(row) = state.buffer.pop_front() {
+                    return Some((Ok(row), state));
+                }
+
+                if state.done {
+                    return None;
+                }
+
+                let res = match state.client.get_query_results(GetQueryResultsInput {
+                    next_token: state.next_token.clone(),
+                    query_execution_id: state.query_execution_id.clone(),
+                    ..Default::default()
+                }).await {
+                    Ok(res) => res,
+                    Err(error) => {
+                        state.done = true;
+                        return Some((Err(error.into()), state));
+                    }
+                };
+
+                let next_token = res.next_token.clone();
+                let (_, rows) = parse_page(res);
+                state.buffer = rows.into_iter().collect();
+
+                if next_token.is_some() {
+                    state.next_token = next_token;
+                } else {
+                    state.done = true;
+                }
+            }
+        });
+
+        Ok((result, stream))
+    }
+
+    async fn start_query(&self, query: &str) -> crate::Result<String> {
         // Start the query
         let query_req = StartQueryExecutionInput {
             client_request_token: Some(uuid::Uuid::new_v4().to_string()),
-            // Use default settings for encrypting results - should use bucket settings. Open to PRs
-            // to make this more configurable
             result_configuration: Some(ResultConfiguration {
-                encryption_configuration: Default::default(),
+                encryption_configuration: self.encryption.clone(),
                 output_location: Some(self.result_bucket.clone()),
             }),
             query_execution_context: Some(QueryExecutionContext{
@@ -82,25 +240,63 @@ impl Athena {
             .query_execution_id.expect("missing execution id");
         tracing::trace!(%query_execution_id);
 
-        // Now we poll Athena waiting for the query to finish. If we get transient API errors we retry up to 5
-        // times before giving up
+        Ok(query_execution_id)
+    }
+
+    /// Issue `StopQueryExecution` for `query_execution_id`, used when the user interrupts the
+    /// process while a query is still in flight so it doesn't keep running (and billing) in
+    /// Athena.
+    async fn stop_query(&self, query_execution_id: &str) -> crate::Result<()> {
+        self.client.stop_query_execution(StopQueryExecutionInput {
+            query_execution_id: query_execution_id.to_owned()
+        }).await.map_err(|error| {
+            tracing::error!(%error, "error cancelling query execution");
+            Error::AthenaError
+        })?;
+
+        Ok(())
+    }
+
+    /// Poll `GetQueryExecution` until the query reaches a terminal state, returning a
+    /// [`QueryResult`] with statistics populated but `data`/`columns`/`rows` left empty for the
+    /// caller to fill in. If the user sends Ctrl-C while this is in flight, the query is
+    /// cancelled server-side via `StopQueryExecution` before returning.
+    async fn wait_for_completion(&self, query_execution_id: &str) -> crate::Result<QueryResult> {
+        // Now we poll Athena waiting for the query to finish. If we get transient API errors we
+        // back off according to `self.retry_policy` before trying again.
         let start = Instant::now();
-        let mut err_count = 0u8;
+        let mut attempt = 0u32;
+
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
         let mut result = loop {
-            let res = match self.client.get_query_execution(GetQueryExecutionInput {
-                query_execution_id: query_execution_id.clone()
-            }).await {
+            let res = tokio::select! {
+                res = self.client.get_query_execution(GetQueryExecutionInput {
+                    query_execution_id: query_execution_id.to_owned()
+                }) => res,
+                _ = &mut ctrl_c => {
+                    tracing::warn!("received Ctrl-C, cancelling query");
+                    self.stop_query(query_execution_id).await?;
+                    Err(Error::CancelledByUser)?
+                }
+            };
+
+            let res = match res {
                 Ok(res) => res,
                 Err(error) => {
-                    err_count += 1;
-
-                    if err_count > 5 {
-                        tracing::error!(%error, "error getting query execution status");
-                        Err(Error::AthenaError)?;
+                    match self.retry_policy.decide(attempt, ErrorClass::Transient) {
+                        RetryDecision::RetryAfter(delay) => {
+                            tracing::debug!(%error, attempt, delay = ?delay, "transient error, retrying");
+                            attempt += 1;
+                            tokio::time::delay_for(delay).await;
+                            continue;
+                        },
+                        RetryDecision::Fail => {
+                            tracing::error!(%error, "error getting query execution status");
+                            Err(Error::AthenaError)?
+                        }
                     }
-
-                    tokio::time::delay_for(Duration::from_millis(250)).await;
-                    continue;
                 }
             };
 
@@ -113,27 +309,35 @@ impl Athena {
                 GetQueryExecutionOutput {
                     query_execution: Some(QueryExecution {
                         query_execution_id: Some(ref query_execution_id),
-                        statistics: Some(QueryExecutionStatistics {
-                            data_scanned_in_bytes: Some(data_scanned_bytes),
-                            query_queue_time_in_millis: Some(query_queue_time_ms),
-                            total_execution_time_in_millis: Some(total_execution_time_ms),
-                            ..
-                        }),
+                        statistics,
                         status: Some(QueryExecutionStatus {
                             state: Some(ref state),
                             ..
                         }),
+                        result_configuration,
                         ..
                     })
                 } if state == "SUCCEEDED" => {
+                    // Athena doesn't guarantee every sub-statistic is populated for every query
+                    // shape (eg DDL/CTAS), so default rather than treating a missing one as the
+                    // query not having succeeded.
+                    let stats = statistics.unwrap_or_default();
+                    let output_location = result_configuration
+                        .and_then(|config| config.output_location)
+                        .unwrap_or_default();
+
                     break QueryResult {
                         query_execution_id: query_execution_id.into(),
-                        data_scanned_bytes,
-                        query_queue_time_ms,
-                        total_execution_time_ms,
+                        data_scanned_bytes: stats.data_scanned_in_bytes.unwrap_or(0),
+                        engine_execution_time_ms: stats.engine_execution_time_in_millis.unwrap_or(0),
+                        query_planning_time_ms: stats.query_planning_time_in_millis.unwrap_or(0),
+                        query_queue_time_ms: stats.query_queue_time_in_millis.unwrap_or(0),
+                        service_processing_time_ms: stats.service_processing_time_in_millis.unwrap_or(0),
+                        total_execution_time_ms: stats.total_execution_time_in_millis.unwrap_or(0),
                         rows: 0,
                         columns: vec![],
-                        data: vec![]
+                        data: vec![],
+                        output_location,
                     }
                 },
                 GetQueryExecutionOutput {
@@ -148,11 +352,21 @@ impl Athena {
                     ..
                 } => {
                     tracing::error!(result = %state, reason = %state_change_reason);
-                    match state.as_str() {
-                        "FAILED" => Err(Error::QueryFailed(state_change_reason.to_owned()))?,
-                        "CANCELLED" => Err(Error::QueryCancelled)?,
-                        _ => unimplemented!()
-                    };
+                    match self.retry_policy.decide(attempt, ErrorClass::Fatal) {
+                        RetryDecision::Fail => {
+                            match state.as_str() {
+                                "FAILED" => Err(Error::QueryFailed(state_change_reason.to_owned()))?,
+                                "CANCELLED" => Err(Error::QueryCancelled)?,
+                                _ => unimplemented!()
+                            };
+                        },
+                        RetryDecision::RetryAfter(delay) => {
+                            tracing::debug!(attempt, delay = ?delay, "retry policy requested a retry after a fatal state");
+                            attempt += 1;
+                            tokio::time::delay_for(delay).await;
+                            continue;
+                        }
+                    }
                 },
                 GetQueryExecutionOutput {
                     query_execution: Some(QueryExecution {
@@ -168,7 +382,14 @@ impl Athena {
                         %state,
                         time_taken = %humantime::format_duration(start.elapsed()).to_string()
                     );
-                    tokio::time::delay_for(Duration::from_secs(1)).await;
+                    tokio::select! {
+                        _ = tokio::time::delay_for(Duration::from_secs(1)) => {},
+                        _ = &mut ctrl_c => {
+                            tracing::warn!("received Ctrl-C, cancelling query");
+                            self.stop_query(query_execution_id).await?;
+                            Err(Error::CancelledByUser)?
+                        }
+                    }
                 },
                 v @ _ => {
                     tracing::debug!(debug = ?v);
@@ -178,51 +399,6 @@ impl Athena {
             // tracing::debug!("query: {} -> {:?}", query_execution_id, status.state);
         };
 
-        // Fetch results in a loop and append
-        let mut result_req = GetQueryResultsInput {
-            next_token: None,
-            query_execution_id: result.query_execution_id.clone(),
-            ..Default::default()
-        };
-
-        loop {
-            let res = self.client.get_query_results(result_req.clone()).await?;
-            let resultset = res.result_set.expect("result_set was none");
-
-            // Get resultset metadata
-            let cols = resultset.result_set_metadata.expect("missing result_set_metadata")
-                .column_info.expect("missing column_info");
-            result.columns = cols.into_iter().map(|c| c.name).collect();
-
-            // 
-            let rows = resultset.rows.expect("missing result_set.rows");
-            rows.into_iter()
-                .skip(1) // headers..
-                .for_each(|row| {
-                    let row = row.data.expect("missing row.data");
-
-                    let new: Vec<String> = row.into_iter()
-                        .map(|field| {
-                            field.var_char_value.unwrap_or_else(String::new)
-                        })
-                        .collect();
-                    
-                    result.append_row(new);
-                });
-
-            tracing::trace!(
-                state = %"SUCCEEDED",
-                rows_read = %result.rows
-            );
-
-            if res.next_token.is_some() {
-                result_req.next_token = res.next_token;
-                continue;
-            }
-
-            break;
-        }
-
         Ok(result)
     }
 }
\ No newline at end of file
@@ -0,0 +1,178 @@
+use crate::Error;
+
+use std::collections::{HashMap, VecDeque};
+
+/// A single bound value, typed so it can be rendered into SQL without the caller needing to
+/// quote/escape it themselves.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Null,
+    Bool(bool),
+    // Kept as the original text so we don't lose precision round-tripping through `f64`.
+    Number(String),
+    String(String),
+}
+
+impl Param {
+    /// Infer a `Param` from the raw string: `null`/`true`/`false`/numeric are typed, everything
+    /// else is a string.
+    pub fn parse(raw: &str) -> Self {
+        if raw.eq_ignore_ascii_case("null") {
+            return Self::Null;
+        }
+
+        if let Ok(value) = raw.parse::<bool>() {
+            return Self::Bool(value);
+        }
+
+        // `f64::parse` also accepts "nan"/"inf"/"infinity" (and case variants), which aren't
+        // valid SQL numeric literals - those should stay quoted strings, not get emitted bare.
+        if let Ok(value) = raw.parse::<f64>() {
+            if value.is_finite() {
+                return Self::Number(raw.to_owned());
+            }
+        }
+
+        Self::String(raw.to_owned())
+    }
+
+    /// Render this value as a SQL literal.
+    fn render(&self) -> String {
+        match self {
+            Self::Null => "NULL".to_owned(),
+            Self::Bool(value) => value.to_string(),
+            Self::Number(value) => value.clone(),
+            Self::String(value) => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+/// Collects `--param` values from the CLI and substitutes them into `?`/`:name` placeholders in
+/// a query string before it's handed to `start_query_execution`.
+#[derive(Default)]
+pub struct ParamBinder {
+    positional: VecDeque<Param>,
+    named: HashMap<String, Param>,
+}
+
+impl ParamBinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind one `--param` value. `name=value` binds `:name`, anything else is queued as the
+    /// next positional `?`.
+    pub fn bind(&mut self, raw: &str) {
+        match raw.split_once('=') {
+            Some((name, value)) if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                self.named.insert(name.to_owned(), Param::parse(value));
+            },
+            _ => self.positional.push_back(Param::parse(raw)),
+        }
+    }
+
+    /// Substitute every `?`/`:name` placeholder outside of string literals with its bound
+    /// value, returning the resulting query text.
+    pub fn apply(&self, query: &str) -> crate::Result<String> {
+        let mut out = String::with_capacity(query.len());
+        let mut chars = query.chars().peekable();
+        let mut positional_idx = 0usize;
+        let mut in_string = false;
+
+        while let Some(c) = chars.next() {
+            if in_string {
+                out.push(c);
+                if c == '\'' {
+                    if chars.peek() == Some(&'\'') {
+                        out.push(chars.next().unwrap());
+                    } else {
+                        in_string = false;
+                    }
+                }
+                continue;
+            }
+
+            match c {
+                '\'' => {
+                    in_string = true;
+                    out.push(c);
+                },
+                '?' => {
+                    let param = self.positional.get(positional_idx)
+                        .ok_or_else(|| Error::MissingParam("?".into()))?;
+                    out.push_str(&param.render());
+                    positional_idx += 1;
+                },
+                ':' if chars.peek().map_or(false, |c| c.is_alphabetic() || *c == '_') => {
+                    let mut name = String::new();
+                    while let Some(&next) = chars.peek() {
+                        if next.is_alphanumeric() || next == '_' {
+                            name.push(next);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+
+                    let param = self.named.get(&name)
+                        .ok_or_else(|| Error::MissingParam(format!(":{}", name)))?;
+                    out.push_str(&param.render());
+                },
+                _ => out.push(c),
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_classifies_known_literals() {
+        assert_eq!(Param::parse("null"), Param::Null);
+        assert_eq!(Param::parse("NULL"), Param::Null);
+        assert_eq!(Param::parse("true"), Param::Bool(true));
+        assert_eq!(Param::parse("42"), Param::Number("42".into()));
+        assert_eq!(Param::parse("-1.5"), Param::Number("-1.5".into()));
+    }
+
+    #[test]
+    fn parse_treats_non_finite_floats_as_strings() {
+        assert_eq!(Param::parse("nan"), Param::String("nan".into()));
+        assert_eq!(Param::parse("inf"), Param::String("inf".into()));
+        assert_eq!(Param::parse("-infinity"), Param::String("-infinity".into()));
+    }
+
+    #[test]
+    fn render_quotes_strings_but_not_numbers() {
+        assert_eq!(Param::parse("inf").render(), "'inf'");
+        assert_eq!(Param::parse("42").render(), "42");
+        assert_eq!(Param::parse("it's").render(), "'it''s'");
+    }
+
+    #[test]
+    fn apply_substitutes_positional_and_named_placeholders() {
+        let mut binder = ParamBinder::new();
+        binder.bind("status=active");
+        binder.bind("3");
+
+        let out = binder.apply("select * from t where id = ? and status = :status").unwrap();
+        assert_eq!(out, "select * from t where id = 3 and status = 'active'");
+    }
+
+    #[test]
+    fn apply_ignores_placeholders_inside_string_literals() {
+        let binder = ParamBinder::new();
+        let out = binder.apply("select '?' as literal").unwrap();
+        assert_eq!(out, "select '?' as literal");
+    }
+
+    #[test]
+    fn apply_errors_on_unbound_placeholder() {
+        let binder = ParamBinder::new();
+        assert!(binder.apply("select ?").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use rand::Rng;
+
+use std::time::Duration;
+
+/// Classification of an error encountered while polling for query status.
+pub enum ErrorClass {
+    Transient,
+    Fatal,
+}
+
+/// The outcome of consulting a [`RetryPolicy`] about a given attempt.
+pub enum RetryDecision {
+    RetryAfter(Duration),
+    Fail,
+}
+
+/// A pluggable policy controlling how `Athena::query` retries while polling for query status.
+pub trait RetryPolicy: Send + Sync {
+    fn decide(&self, attempt: u32, class: ErrorClass) -> RetryDecision;
+}
+
+/// Full-jitter exponential backoff: `delay = random_between(0, min(cap, base * 2^attempt))`.
+pub struct DefaultRetryPolicy {
+    base: Duration,
+    cap: Duration,
+    max_attempts: u32,
+}
+
+impl DefaultRetryPolicy {
+    pub fn new(base: Duration, cap: Duration, max_attempts: u32) -> Self {
+        Self { base, cap, max_attempts }
+    }
+}
+
+impl Default for DefaultRetryPolicy {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(250), Duration::from_secs(30), 5)
+    }
+}
+
+impl RetryPolicy for DefaultRetryPolicy {
+    fn decide(&self, attempt: u32, class: ErrorClass) -> RetryDecision {
+        match class {
+            ErrorClass::Fatal => RetryDecision::Fail,
+            ErrorClass::Transient if attempt >= self.max_attempts => RetryDecision::Fail,
+            ErrorClass::Transient => {
+                let exp = self.base.as_millis().saturating_mul(1u128 << attempt.min(63));
+                let capped = exp.min(self.cap.as_millis()).max(1);
+
+                let jittered = rand::thread_rng().gen_range(0..=capped) as u64;
+                RetryDecision::RetryAfter(Duration::from_millis(jittered))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fatal_always_fails() {
+        let policy = DefaultRetryPolicy::default();
+        assert!(matches!(policy.decide(0, ErrorClass::Fatal), RetryDecision::Fail));
+        assert!(matches!(policy.decide(100, ErrorClass::Fatal), RetryDecision::Fail));
+    }
+
+    #[test]
+    fn transient_fails_once_max_attempts_reached() {
+        let policy = DefaultRetryPolicy::new(Duration::from_millis(10), Duration::from_secs(1), 3);
+        assert!(matches!(policy.decide(3, ErrorClass::Transient), RetryDecision::Fail));
+        assert!(matches!(policy.decide(4, ErrorClass::Transient), RetryDecision::Fail));
+    }
+
+    #[test]
+    fn transient_backoff_is_jittered_and_capped() {
+        let policy = DefaultRetryPolicy::new(Duration::from_millis(100), Duration::from_millis(250), 5);
+
+        for attempt in 0..5 {
+            match policy.decide(attempt, ErrorClass::Transient) {
+                RetryDecision::RetryAfter(delay) => assert!(delay <= Duration::from_millis(250)),
+                RetryDecision::Fail => panic!("expected a retry for attempt {}", attempt),
+            }
+        }
+    }
+}
@@ -1,17 +1,126 @@
 use byte_unit::Byte;
+use rusoto_athena::EncryptionConfiguration;
+use serde_json::Value;
 
+use std::str::FromStr;
 use std::time::Duration;
 
+/// Result encryption scheme for `ResultConfiguration`, selected via `--encryption`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionMode {
+    SseS3,
+    SseKms,
+    CseKms,
+}
+
+impl EncryptionMode {
+    /// Whether this mode requires a `--kms-key` to be supplied.
+    pub fn requires_kms_key(self) -> bool {
+        matches!(self, Self::SseKms | Self::CseKms)
+    }
+
+    /// Build the `EncryptionConfiguration` rusoto expects on `ResultConfiguration`.
+    pub fn to_encryption_configuration(self, kms_key: Option<String>) -> EncryptionConfiguration {
+        EncryptionConfiguration {
+            encryption_option: match self {
+                Self::SseS3 => "SSE_S3".into(),
+                Self::SseKms => "SSE_KMS".into(),
+                Self::CseKms => "CSE_KMS".into(),
+            },
+            kms_key,
+        }
+    }
+}
+
+impl FromStr for EncryptionMode {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s {
+            "sse_s3" => Ok(Self::SseS3),
+            "sse_kms" => Ok(Self::SseKms),
+            "cse_kms" => Ok(Self::CseKms),
+            other => Err(crate::Error::InvalidEncryption(other.into())),
+        }
+    }
+}
+
+/// Output format for a [`QueryResult`], selected via `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    // Human-readable AsciiTable rendering (the default).
+    Table,
+    Csv,
+    Tsv,
+    // A single JSON array of row objects, keyed by column name.
+    Json,
+    // Newline-delimited JSON, one row object per line.
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s {
+            "table" => Ok(Self::Table),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            other => Err(crate::Error::InvalidFormat(other.into())),
+        }
+    }
+}
+
+/// Join a row with `delimiter` into a single line, wrapping any field that contains the
+/// delimiter, a `"` or a newline in double quotes (with embedded `"` doubled).
+fn delimited_row(row: &[String], delimiter: char) -> String {
+    row.iter()
+        .map(|field| {
+            if field.contains(delimiter) || field.contains('"') || field.contains('\n') {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+/// Build a JSON object for a single row, keyed by column name.
+fn row_as_json(columns: &[String], row: &[String]) -> Value {
+    let mut obj = serde_json::Map::with_capacity(columns.len());
+    for (col, val) in columns.iter().zip(row.iter()) {
+        obj.insert(col.clone(), Value::String(val.clone()));
+    }
+    Value::Object(obj)
+}
+
+/// Render a single row in the given format, without surrounding structure (eg no `[`/`]` for
+/// `Json`), so it can be printed incrementally as rows stream in. `run()` rejects `--stream`
+/// with `Table`/`Json`, so they're never actually hit here.
+pub fn format_row(columns: &[String], row: &[String], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Table | OutputFormat::Json | OutputFormat::Csv => delimited_row(row, ','),
+        OutputFormat::Tsv => delimited_row(row, '\t'),
+        OutputFormat::Ndjson => row_as_json(columns, row).to_string(),
+    }
+}
+
 pub struct QueryResult {
     pub query_execution_id: String,
     pub data: Vec<Vec<String>>,
     pub data_scanned_bytes: i64,
-    // query_execution_time_ms: i64,
-    // query_planning_time_ms: i64,
+    pub engine_execution_time_ms: i64,
+    pub query_planning_time_ms: i64,
     pub query_queue_time_ms: i64,
+    pub service_processing_time_ms: i64,
     pub rows: i64,
     pub columns: Vec<String>,
-    pub total_execution_time_ms: i64
+    pub total_execution_time_ms: i64,
+    // S3 location the raw results were written to.
+    pub output_location: String,
 }
 
 impl QueryResult {
@@ -30,4 +139,90 @@ impl QueryResult {
         let time = Duration::from_millis(self.total_execution_time_ms as u64);
         humantime::format_duration(time).to_string()
     }
+
+    /// A structured timing/cost breakdown, printed when `--stats` is passed.
+    pub fn stats_summary(&self) -> String {
+        let duration = |ms: i64| humantime::format_duration(Duration::from_millis(ms as u64)).to_string();
+
+        format!(
+            "data scanned:     {}\nqueue time:       {}\nplanning time:    {}\nengine time:      {}\nservice time:     {}\ntotal time:       {}\noutput location:  {}",
+            self.data_scanned(),
+            duration(self.query_queue_time_ms),
+            duration(self.query_planning_time_ms),
+            duration(self.engine_execution_time_ms),
+            duration(self.service_processing_time_ms),
+            self.total_time(),
+            self.output_location
+        )
+    }
+
+    /// Render the full resultset in the given format. Callers should render `Table` through
+    /// `ascii_table::AsciiTable` instead - passing it here returns `Error::TableNotRenderable`.
+    pub fn render(&self, format: OutputFormat) -> crate::Result<String> {
+        match format {
+            OutputFormat::Table => Err(crate::Error::TableNotRenderable),
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let mut out = String::new();
+                out.push_str(&format_row(&self.columns, &self.columns, format));
+                out.push('\n');
+                for row in &self.data {
+                    out.push_str(&format_row(&self.columns, row, format));
+                    out.push('\n');
+                }
+                Ok(out)
+            },
+            OutputFormat::Json => {
+                let rows: Vec<Value> = self.data.iter().map(|row| row_as_json(&self.columns, row)).collect();
+                Ok(serde_json::to_string_pretty(&rows).unwrap_or_default())
+            },
+            OutputFormat::Ndjson => {
+                Ok(self.data.iter()
+                    .map(|row| row_as_json(&self.columns, row).to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result() -> QueryResult {
+        QueryResult {
+            query_execution_id: "abc-123".into(),
+            data: vec![vec!["1".into(), "has,comma".into()]],
+            data_scanned_bytes: 0,
+            engine_execution_time_ms: 0,
+            query_planning_time_ms: 0,
+            query_queue_time_ms: 0,
+            service_processing_time_ms: 0,
+            rows: 1,
+            columns: vec!["id".into(), "name".into()],
+            total_execution_time_ms: 0,
+            output_location: "s3://bucket/path".into(),
+        }
+    }
+
+    #[test]
+    fn format_row_quotes_fields_containing_the_delimiter() {
+        let columns = vec!["id".to_owned(), "name".to_owned()];
+        let row = vec!["1".to_owned(), "has,comma".to_owned()];
+
+        assert_eq!(format_row(&columns, &row, OutputFormat::Csv), "1,\"has,comma\"");
+        assert_eq!(format_row(&columns, &row, OutputFormat::Tsv), "1\thas,comma");
+        assert_eq!(format_row(&columns, &row, OutputFormat::Ndjson), r#"{"id":"1","name":"has,comma"}"#);
+    }
+
+    #[test]
+    fn render_table_is_rejected() {
+        assert!(matches!(sample_result().render(OutputFormat::Table), Err(crate::Error::TableNotRenderable)));
+    }
+
+    #[test]
+    fn render_csv_includes_a_header_row() {
+        let out = sample_result().render(OutputFormat::Csv).unwrap();
+        assert_eq!(out, "id,name\n1,\"has,comma\"\n");
+    }
 }
\ No newline at end of file
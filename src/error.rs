@@ -3,13 +3,19 @@ pub enum Error {
     AthenaError,
     ConnectionError,
     GetQueryResultsError(String),
+    InvalidEncryption(String),
+    InvalidFormat(String),
     InvalidProxy(http::uri::InvalidUri),
     InvalidRegion,
     InvalidSql(sqlparser::parser::ParserError),
+    MissingKmsKey,
+    MissingParam(String),
     TracingFormat,
     QueryError,
     QueryCancelled,
+    CancelledByUser,
     QueryFailed(String),
+    TableNotRenderable,
 
     IoErr(std::io::Error),
 }
@@ -23,13 +29,19 @@ impl std::fmt::Display for Error {
             Self::AthenaError => write!(f, "Athena API error encountered"),
             Self::ConnectionError => write!(f, "Error connecting to AWS"),
             Self::GetQueryResultsError(error) => write!(f, "Error getting query results: {}", error),
+            Self::InvalidFormat(format) => write!(f, "Unknown output format: {}", format),
             Self::InvalidProxy(error) => write!(f, "Invalid proxy URI: {}", error),
             Self::InvalidRegion => write!(f, "Invalid region specified"),
             Self::InvalidSql(error) => write!(f, "Invalid SQL in provided file: {}", error),
+            Self::InvalidEncryption(mode) => write!(f, "Unknown encryption mode: {}", mode),
+            Self::MissingKmsKey => write!(f, "--kms-key is required when --encryption is sse_kms or cse_kms"),
+            Self::MissingParam(placeholder) => write!(f, "No value bound for parameter {}", placeholder),
             Self::TracingFormat => write!(f, "ATHENACLI_LOG contained invalid format"),
             Self::QueryError => write!(f, "Unknown error executing query"),
             Self::QueryCancelled => write!(f, "Query cancelled"),
+            Self::CancelledByUser => write!(f, "Query cancelled by user (Ctrl-C)"),
             Self::QueryFailed(reason) => write!(f, "Query failed: {}", reason),
+            Self::TableNotRenderable => write!(f, "OutputFormat::Table cannot be rendered as text, render it through AsciiTable instead"),
 
             Self::IoErr(error) => write!(f, "I/O error: {}", error),
         }
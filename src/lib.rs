@@ -1,10 +1,14 @@
 use ascii_table::{AsciiTable, Column, Align};
+use byte_unit::Byte;
+use futures::{stream, StreamExt};
 use structopt::StructOpt;
 
 use std::path::PathBuf;
+use std::time::Duration;
 
 mod athena;
 mod error;
+use athena::{format_row, DefaultRetryPolicy, EncryptionMode, OutputFormat, ParamBinder, RetryPolicy};
 use error::Error;
 
 type Result<T> = std::result::Result<T, error::Error>;
@@ -40,6 +44,83 @@ struct Config {
     /// Logging verbosity (repeat for more detail)
     #[structopt(short = "v", parse(from_occurrences))]
     verbose: u64,
+
+    /// base delay (ms) used for exponential backoff when retrying transient API errors
+    #[structopt(long = "retry-base-ms", default_value = "250")]
+    retry_base_ms: u64,
+
+    /// maximum delay (ms) a single retry backoff will be capped at
+    #[structopt(long = "retry-cap-ms", default_value = "30000")]
+    retry_cap_ms: u64,
+
+    /// maximum number of retry attempts before giving up on a transient error
+    #[structopt(long = "retry-max-attempts", default_value = "5")]
+    retry_max_attempts: u32,
+
+    /// stream rows to stdout as each page of results arrives instead of buffering the whole
+    /// resultset before printing, keeping memory use bounded on large scans
+    #[structopt(long = "stream")]
+    stream: bool,
+
+    /// output format for query results
+    #[structopt(long = "format", possible_values = &["table", "csv", "tsv", "json", "ndjson"], default_value = "table")]
+    format: OutputFormat,
+
+    /// bind a query parameter, can be repeated. `name=value` binds `:name`, anything else binds
+    /// the next positional `?` in order
+    #[structopt(long = "param")]
+    param: Option<Vec<String>>,
+
+    /// run up to N queries concurrently (ignored in --stream mode)
+    #[structopt(long = "jobs", short = "j", default_value = "1")]
+    jobs: usize,
+
+    /// keep running the remaining queries if one fails, instead of aborting the batch
+    #[structopt(long = "continue-on-error")]
+    continue_on_error: bool,
+
+    /// encrypt query results written to the results bucket
+    #[structopt(long = "encryption", possible_values = &["sse_s3", "sse_kms", "cse_kms"])]
+    encryption: Option<EncryptionMode>,
+
+    /// KMS key id to use, required when --encryption is sse_kms or cse_kms
+    #[structopt(long = "kms-key")]
+    kms_key: Option<String>,
+
+    /// print a timing/cost breakdown (queue, planning, engine, service time, S3 output
+    /// location) after each query
+    #[structopt(long = "stats")]
+    stats: bool,
+}
+
+/// Print a single query's resultset per the selected `--format`, preceded by a stats breakdown
+/// if `stats` is set.
+fn print_result(result: athena::QueryResult, format: OutputFormat, stats: bool) -> Result<()> {
+    if stats {
+        println!("{}", result.stats_summary());
+    }
+
+    if result.rows == 0 {
+        return Ok(());
+    }
+
+    if format == OutputFormat::Table {
+        let mut table = AsciiTable::default();
+
+        for (idx, col) in result.columns.iter().enumerate() {
+            table.columns.insert(idx, Column {
+                header: col.into(),
+                align: Align::Left,
+                ..Default::default()
+            });
+        }
+
+        table.print(result.data);
+    } else {
+        println!("{}", result.render(format)?);
+    }
+
+    Ok(())
 }
 
 pub async fn run() -> Result<()> {
@@ -74,6 +155,11 @@ pub async fn run() -> Result<()> {
         std::process::exit(1);
     }
 
+    if args.stream && matches!(args.format, OutputFormat::Json | OutputFormat::Table) {
+        tracing::error!("--format {:?} cannot be streamed a row at a time, use --format csv/tsv/ndjson instead", args.format);
+        std::process::exit(1);
+    }
+
     let queries: Vec<_> = match args.file {
         Some(ref path) if !path.exists() => {
             tracing::error!(path = %path.display(), "input file does not exist");
@@ -90,7 +176,28 @@ pub async fn run() -> Result<()> {
         None => args.command.unwrap(),
     };
 
-    let athena = athena::Athena::new(&args.region, &args.database, &args.result_bucket, args.workgroup.clone())?;
+    let mut binder = ParamBinder::new();
+    for param in args.param.iter().flatten() {
+        binder.bind(param);
+    }
+    let queries: Vec<String> = queries.iter()
+        .map(|query| binder.apply(query))
+        .collect::<Result<_>>()?;
+
+    let retry_policy: Box<dyn RetryPolicy> = Box::new(DefaultRetryPolicy::new(
+        Duration::from_millis(args.retry_base_ms),
+        Duration::from_millis(args.retry_cap_ms),
+        args.retry_max_attempts
+    ));
+    let athena = athena::Athena::new(
+        &args.region,
+        &args.database,
+        &args.result_bucket,
+        args.workgroup.clone(),
+        retry_policy,
+        args.encryption,
+        args.kms_key.clone()
+    )?;
 
     tracing::debug!(
         region = %args.region,
@@ -99,8 +206,84 @@ pub async fn run() -> Result<()> {
         workgroup = ?args.workgroup,
         "executing query"
     );
-    for query in queries.into_iter() {
-        match athena.query(&query).await {
+    if args.stream {
+        if args.jobs > 1 {
+            tracing::warn!(jobs = args.jobs, "--jobs is ignored in --stream mode, running sequentially");
+        }
+
+        for query in queries.into_iter() {
+            let (result, mut rows) = match athena.query_stream(&query).await {
+                Ok(pair) => pair,
+                Err(error) => {
+                    tracing::error!(%error, "error running query");
+                    if args.continue_on_error {
+                        continue;
+                    }
+                    return Err(error);
+                }
+            };
+
+            if args.stats {
+                println!("{}", result.stats_summary());
+            }
+
+            if args.format != OutputFormat::Ndjson {
+                println!("{}", format_row(&result.columns, &result.columns, args.format));
+            }
+
+            while let Some(row) = rows.next().await {
+                match row {
+                    Ok(row) => println!("{}", format_row(&result.columns, &row, args.format)),
+                    Err(error) => {
+                        tracing::error!(%error, "error streaming query results");
+                        if args.continue_on_error {
+                            break;
+                        }
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    // Run up to `--jobs` queries concurrently, preserving submission order in the printed
+    // output regardless of completion order. Without --continue-on-error, `failed_fast` is flipped
+    // as soon as any query errors so the stream stops *submitting* further queries - queries
+    // already in flight (up to --jobs of them) still run to completion, since there's no way to
+    // un-submit them once Athena has accepted them. --continue-on-error only changes whether we
+    // keep going after a failure, not how much it costs to find out about one.
+    let failed_fast = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let continue_on_error = args.continue_on_error;
+
+    let results: Vec<Result<athena::QueryResult>> = stream::iter(queries.into_iter())
+        .take_while(|_| {
+            let failed_fast = failed_fast.clone();
+            async move { continue_on_error || !failed_fast.load(std::sync::atomic::Ordering::SeqCst) }
+        })
+        .map(|query| {
+            let failed_fast = failed_fast.clone();
+            async move {
+                let result = athena.query(&query).await;
+                if result.is_err() {
+                    failed_fast.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+                result
+            }
+        })
+        .buffered(args.jobs.max(1))
+        .collect()
+        .await;
+
+    let mut data_scanned_bytes = 0i64;
+    let mut total_execution_time_ms = 0i64;
+    let mut succeeded = 0usize;
+    let mut failed = 0usize;
+    let mut first_error = None;
+
+    for result in results {
+        match result {
             Ok(result) => {
                 tracing::info!(
                     rows = %result.rows,
@@ -109,27 +292,34 @@ pub async fn run() -> Result<()> {
                     "query complete"
                 );
 
-                // Return early if we have an empty resultset
-                if result.rows == 0 { return Ok(()); }
-
-                // Now set up our table
-                let mut table = AsciiTable::default();
+                data_scanned_bytes += result.data_scanned_bytes;
+                total_execution_time_ms += result.total_execution_time_ms;
+                succeeded += 1;
 
-                for (idx, col) in result.columns.iter().enumerate() {
-                    table.columns.insert(idx, Column {
-                        header: col.into(),
-                        align: Align::Left,
-                        ..Default::default()
-                    });
-                }
-
-                table.print(result.data);
+                print_result(result, args.format, args.stats)?;
             },
             Err(error) => {
                 tracing::error!(%error, "error running query");
-                Err(error)?
+                failed += 1;
+                first_error.get_or_insert(error);
             }
-        };
+        }
+    }
+
+    if succeeded + failed > 1 {
+        println!(
+            "{} succeeded, {} failed, {} scanned, {} total query time",
+            succeeded,
+            failed,
+            Byte::from_bytes(data_scanned_bytes as u128).get_appropriate_unit(false),
+            humantime::format_duration(Duration::from_millis(total_execution_time_ms as u64))
+        );
+    }
+
+    if !continue_on_error {
+        if let Some(error) = first_error {
+            return Err(error);
+        }
     }
 
     Ok(())